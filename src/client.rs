@@ -0,0 +1,149 @@
+//! A [`Client`] that owns a spawned `kak -ui json` child process, turning its stdout into a
+//! stream of [`IncomingRequest`]s and its stdin into a sink for [`OutgoingRequest`]s.
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::{IncomingRequest, OutgoingRequest};
+
+/// An error communicating with a kakoune child process.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error reading from or writing to the child process.
+    Io(std::io::Error),
+    /// The child wrote something that wasn't a valid [`IncomingRequest`].
+    Json(serde_json::Error),
+    /// The child's stdout closed, meaning the kakoune process has exited.
+    ChildExited,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error communicating with kakoune: {e}"),
+            Error::Json(e) => write!(f, "failed to parse request from kakoune: {e}"),
+            Error::ChildExited => write!(f, "kakoune process exited"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::ChildExited => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// A running `kak -ui json` child process, exposing its requests as an iterator and letting
+/// callers send [`OutgoingRequest`]s back over its stdin.
+pub struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    /// Spawns `kak -ui json`, optionally attaching to an existing `session` (via `-c`) and
+    /// passing any `extra_args` through to kakoune.
+    pub fn spawn(session: Option<&str>, extra_args: &[&str]) -> Result<Self, Error> {
+        let mut args = vec!["-ui", "json"];
+        if let Some(session) = session {
+            args.push("-c");
+            args.push(session);
+        }
+        args.extend(extra_args);
+
+        let mut child = Command::new("kak")
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .expect("child was spawned with a piped stdin");
+        let stdout = child
+            .stdout
+            .take()
+            .expect("child was spawned with a piped stdout");
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Returns an iterator over [`IncomingRequest`]s parsed from the child's stdout, one per
+    /// line. Once the child closes stdout, the iterator yields a final `Err(Error::ChildExited)`
+    /// and then ends, so callers can distinguish "kakoune exited" from "no request buffered yet".
+    pub fn requests(&mut self) -> Requests<'_> {
+        Requests {
+            client: self,
+            exhausted: false,
+        }
+    }
+
+    /// Writes a newline-terminated JSON frame for `request` to the child's stdin.
+    pub fn send(&mut self, request: &OutgoingRequest) -> Result<(), Error> {
+        serde_json::to_writer(&mut self.stdin, request)?;
+        self.stdin.write_all(b"\n")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Returns `true` if the child process has exited.
+    pub fn has_exited(&mut self) -> Result<bool, Error> {
+        Ok(self.child.try_wait()?.is_some())
+    }
+}
+
+/// Iterator over [`IncomingRequest`]s read from a [`Client`]'s stdout, returned by
+/// [`Client::requests`].
+pub struct Requests<'a> {
+    client: &'a mut Client,
+    exhausted: bool,
+}
+
+impl Iterator for Requests<'_> {
+    type Item = Result<IncomingRequest, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        let mut line = String::new();
+        match self.client.stdout.read_line(&mut line) {
+            Ok(0) => {
+                self.exhausted = true;
+                match self.client.child.try_wait() {
+                    Ok(_) => Some(Err(Error::ChildExited)),
+                    Err(e) => Some(Err(Error::Io(e))),
+                }
+            }
+            Ok(_) => Some(serde_json::from_str(line.trim_end()).map_err(Error::from)),
+            Err(e) => {
+                self.exhausted = true;
+                Some(Err(Error::Io(e)))
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! A typed model of kakoune's key-press syntax (`<c-x>`, `<a-x>`, `<ret>`, bare characters, ...)
+//! so callers don't have to hand-build key strings for [`crate::OutgoingRequest::Keys`].
+
+use std::fmt;
+
+/// The non-modifier part of a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Char(char),
+    Ret,
+    Esc,
+    Tab,
+    Space,
+    Backspace,
+    Del,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+/// The modifier keys held down alongside a [`KeyCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+/// A single kakoune key press: a [`KeyCode`] plus any held [`Modifiers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+/// An error parsing a key string that isn't valid kakoune key syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyParseError(String);
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid kakoune key: {}", self.0)
+    }
+}
+
+impl std::error::Error for KeyParseError {}
+
+impl Key {
+    /// Creates a [`Key`] with no modifiers.
+    pub fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    /// Parses a single key in kakoune's syntax: a bare character, `<lt>`/`<gt>` for literal
+    /// `<`/`>`, a named key like `<ret>`/`<tab>`/`<f1>`, or a modified key like `<c-x>`/`<a-x>`/
+    /// `<s-tab>`.
+    pub fn parse(s: &str) -> Result<Self, KeyParseError> {
+        match s.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+            Some(inner) => Self::parse_named(inner, s),
+            None => {
+                let mut chars = s.chars();
+                let ch = chars
+                    .next()
+                    .ok_or_else(|| KeyParseError(format!("{s:?} is empty")))?;
+                if chars.next().is_some() {
+                    return Err(KeyParseError(format!(
+                        "{s:?} is not a single character or a <...> key"
+                    )));
+                }
+                Ok(Key::new(KeyCode::Char(ch)))
+            }
+        }
+    }
+
+    fn parse_named(inner: &str, original: &str) -> Result<Self, KeyParseError> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = inner;
+        loop {
+            if let Some(r) = rest.strip_prefix("c-") {
+                modifiers.ctrl = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("a-") {
+                modifiers.alt = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("s-") {
+                modifiers.shift = true;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest {
+            "ret" => KeyCode::Ret,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Space,
+            "backspace" => KeyCode::Backspace,
+            "del" => KeyCode::Del,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "lt" => KeyCode::Char('<'),
+            "gt" => KeyCode::Char('>'),
+            _ if rest.chars().count() == 1 => KeyCode::Char(rest.chars().next().unwrap()),
+            _ if rest.starts_with('f') => {
+                let n: u8 = rest[1..]
+                    .parse()
+                    .map_err(|_| KeyParseError(format!("unknown key name in {original:?}")))?;
+                if !(1..=12).contains(&n) {
+                    return Err(KeyParseError(format!(
+                        "function key out of range in {original:?}"
+                    )));
+                }
+                KeyCode::F(n)
+            }
+            _ => return Err(KeyParseError(format!("unknown key name in {original:?}"))),
+        };
+
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers == Modifiers::default() {
+            match self.code {
+                KeyCode::Char('<') => return write!(f, "<lt>"),
+                KeyCode::Char('>') => return write!(f, "<gt>"),
+                KeyCode::Char(c) => return write!(f, "{c}"),
+                _ => {}
+            }
+        }
+
+        write!(f, "<")?;
+        if self.modifiers.ctrl {
+            write!(f, "c-")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "a-")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "s-")?;
+        }
+        match self.code {
+            KeyCode::Char('<') => write!(f, "lt")?,
+            KeyCode::Char('>') => write!(f, "gt")?,
+            KeyCode::Char(c) => write!(f, "{c}")?,
+            KeyCode::Ret => write!(f, "ret")?,
+            KeyCode::Esc => write!(f, "esc")?,
+            KeyCode::Tab => write!(f, "tab")?,
+            KeyCode::Space => write!(f, "space")?,
+            KeyCode::Backspace => write!(f, "backspace")?,
+            KeyCode::Del => write!(f, "del")?,
+            KeyCode::Up => write!(f, "up")?,
+            KeyCode::Down => write!(f, "down")?,
+            KeyCode::Left => write!(f, "left")?,
+            KeyCode::Right => write!(f, "right")?,
+            KeyCode::Home => write!(f, "home")?,
+            KeyCode::End => write!(f, "end")?,
+            KeyCode::PageUp => write!(f, "pageup")?,
+            KeyCode::PageDown => write!(f, "pagedown")?,
+            KeyCode::F(n) => write!(f, "f{n}")?,
+        }
+        write!(f, ">")
+    }
+}
+
+impl crate::OutgoingRequest {
+    /// Builds an [`crate::OutgoingRequest::Keys`] request from typed [`Key`]s, rendering each to
+    /// kakoune's key syntax (e.g. `<c-x>`).
+    pub fn from_keys(keys: impl IntoIterator<Item = Key>) -> Self {
+        crate::OutgoingRequest::Keys(keys.into_iter().map(|key| key.to_string()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modified(code: KeyCode, modifiers: Modifiers) -> Key {
+        Key { code, modifiers }
+    }
+
+    #[test]
+    fn plain_ascii_char_round_trips() {
+        let key = Key::parse("a").unwrap();
+        assert_eq!(key, Key::new(KeyCode::Char('a')));
+        assert_eq!(key.to_string(), "a");
+    }
+
+    #[test]
+    fn plain_non_ascii_char_round_trips() {
+        let key = Key::parse("é").unwrap();
+        assert_eq!(key, Key::new(KeyCode::Char('é')));
+        assert_eq!(key.to_string(), "é");
+    }
+
+    #[test]
+    fn named_keys_round_trip() {
+        let cases = [
+            (KeyCode::Ret, "<ret>"),
+            (KeyCode::Esc, "<esc>"),
+            (KeyCode::Tab, "<tab>"),
+            (KeyCode::Space, "<space>"),
+            (KeyCode::Backspace, "<backspace>"),
+            (KeyCode::Del, "<del>"),
+            (KeyCode::Up, "<up>"),
+            (KeyCode::Down, "<down>"),
+            (KeyCode::Left, "<left>"),
+            (KeyCode::Right, "<right>"),
+            (KeyCode::Home, "<home>"),
+            (KeyCode::End, "<end>"),
+            (KeyCode::PageUp, "<pageup>"),
+            (KeyCode::PageDown, "<pagedown>"),
+            (KeyCode::F(1), "<f1>"),
+            (KeyCode::F(12), "<f12>"),
+        ];
+        for (code, rendered) in cases {
+            let key = Key::new(code);
+            assert_eq!(key.to_string(), rendered);
+            assert_eq!(Key::parse(rendered).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn lt_and_gt_escape_round_trip() {
+        for (code, rendered) in [(KeyCode::Char('<'), "<lt>"), (KeyCode::Char('>'), "<gt>")] {
+            let key = Key::new(code);
+            assert_eq!(key.to_string(), rendered);
+            assert_eq!(Key::parse(rendered).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn modifier_combinations_round_trip() {
+        let cases = [
+            (
+                Modifiers {
+                    ctrl: true,
+                    alt: false,
+                    shift: false,
+                },
+                "<c-x>",
+            ),
+            (
+                Modifiers {
+                    ctrl: false,
+                    alt: true,
+                    shift: false,
+                },
+                "<a-x>",
+            ),
+            (
+                Modifiers {
+                    ctrl: false,
+                    alt: false,
+                    shift: true,
+                },
+                "<s-tab>",
+            ),
+            (
+                Modifiers {
+                    ctrl: true,
+                    alt: true,
+                    shift: true,
+                },
+                "<c-a-s-x>",
+            ),
+        ];
+        for (modifiers, rendered) in cases {
+            let code = if rendered.ends_with("tab>") {
+                KeyCode::Tab
+            } else {
+                KeyCode::Char('x')
+            };
+            let key = modified(code, modifiers);
+            assert_eq!(key.to_string(), rendered);
+            assert_eq!(Key::parse(rendered).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn modified_non_ascii_char_round_trips() {
+        let key = modified(
+            KeyCode::Char('é'),
+            Modifiers {
+                ctrl: true,
+                alt: false,
+                shift: false,
+            },
+        );
+        assert_eq!(key.to_string(), "<c-é>");
+        assert_eq!(Key::parse("<c-é>").unwrap(), key);
+    }
+
+    #[test]
+    fn function_key_out_of_range_is_rejected() {
+        assert!(Key::parse("<f0>").is_err());
+        assert!(Key::parse("<f13>").is_err());
+    }
+
+    #[test]
+    fn unknown_named_key_is_rejected() {
+        assert!(Key::parse("<bogus>").is_err());
+    }
+
+    #[test]
+    fn multi_character_bare_string_is_rejected() {
+        assert!(Key::parse("ab").is_err());
+        assert!(Key::parse("").is_err());
+    }
+}
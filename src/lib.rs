@@ -8,42 +8,37 @@
 //!
 //! Basic usage:
 //!
-//!```rust
-//! use std::io::{BufRead, BufReader};
-//! use std::process::{Command, Child, Stdio};
-//! use kak_ui::{IncomingRequest, OutgoingRequest};
+//!```rust,no_run
+//! use kak_ui::client::Client;
+//! use kak_ui::OutgoingRequest;
 //!
-//! let kak_child_process = Command::new("kak")
-//!     .args(&["-ui", "json"])
-//!     .stdout(Stdio::piped())
-//!     .stdin(Stdio::piped())
-//!     .spawn()
-//!     .unwrap();
+//! let mut client = Client::spawn(None, &[]).unwrap();
 //!
-//! let incoming_request: IncomingRequest = serde_json::from_str(
-//!     &BufReader::new(kak_child_process.stdout.unwrap())
-//!         .lines()
-//!         .next()
-//!         .unwrap()
-//!         .unwrap(),
-//! )
-//! .unwrap();
+//! let incoming_request = client.requests().next().unwrap().unwrap();
 //!
 //! let outgoing_request = OutgoingRequest::Keys(vec!["<esc>:q<ret>".to_string()]);
-//! serde_json::to_writer(kak_child_process.stdin.unwrap(), &outgoing_request).unwrap();
+//! client.send(&outgoing_request).unwrap();
 //!```
 
 // TODO: Add links to kakoune docs
 
+pub mod client;
+pub mod key;
+pub mod render;
+pub mod screen;
+
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
-/// A color in kakoune. Currently, this is a newtype wrapper around String.
-#[derive(Debug, Clone)]
+/// A color in kakoune.
+///
+/// The `RGB`/`RGBA` variants hold parsed components so callers can do real color
+/// math instead of re-parsing a hex string every time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KakColor {
-    RGB(String),
-    RGBA(String),
+    RGB { r: u8, g: u8, b: u8 },
+    RGBA { r: u8, g: u8, b: u8, a: u8 },
     Black,
     Red,
     Green,
@@ -55,6 +50,21 @@ pub enum KakColor {
     Default,
 }
 
+/// Parses a single `RRGGBB`/`RRGGBBAA`-style hex string into its bytes.
+///
+/// Returns `None` if `hex` isn't exactly `N * 2` hex digits, so callers never
+/// have to worry about slicing into the middle of a malformed or short string.
+fn parse_hex_bytes<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for i in 0..N {
+        out[i] = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
 impl<'de> Deserialize<'de> for KakColor {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -87,10 +97,22 @@ impl<'de> Visitor<'de> for ColorVisitor {
             "white" => Ok(KakColor::White),
             "default" => Ok(KakColor::Default),
             x => {
-                if &x[..4] == "rgb:" {
-                    Ok(KakColor::RGB((&x[4..]).to_string()))
-                } else if &x[..5] == "rgba:" {
-                    Ok(KakColor::RGBA((&x[5..]).to_string()))
+                if let Some(hex) = x.strip_prefix("rgb:") {
+                    let [r, g, b] = parse_hex_bytes(hex).ok_or_else(|| {
+                        serde::de::Error::invalid_value(
+                            serde::de::Unexpected::Str(x),
+                            &"rgb:RRGGBB",
+                        )
+                    })?;
+                    Ok(KakColor::RGB { r, g, b })
+                } else if let Some(hex) = x.strip_prefix("rgba:") {
+                    let [r, g, b, a] = parse_hex_bytes(hex).ok_or_else(|| {
+                        serde::de::Error::invalid_value(
+                            serde::de::Unexpected::Str(x),
+                            &"rgba:RRGGBBAA",
+                        )
+                    })?;
+                    Ok(KakColor::RGBA { r, g, b, a })
                 } else {
                     Err(serde::de::Error::invalid_value(
                         serde::de::Unexpected::Str(x),
@@ -102,8 +124,33 @@ impl<'de> Visitor<'de> for ColorVisitor {
     }
 }
 
+impl Serialize for KakColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            KakColor::RGB { r, g, b } => {
+                serializer.serialize_str(&format!("rgb:{r:02x}{g:02x}{b:02x}"))
+            }
+            KakColor::RGBA { r, g, b, a } => {
+                serializer.serialize_str(&format!("rgba:{r:02x}{g:02x}{b:02x}{a:02x}"))
+            }
+            KakColor::Black => serializer.serialize_str("black"),
+            KakColor::Red => serializer.serialize_str("red"),
+            KakColor::Green => serializer.serialize_str("green"),
+            KakColor::Yellow => serializer.serialize_str("yellow"),
+            KakColor::Blue => serializer.serialize_str("blue"),
+            KakColor::Purple => serializer.serialize_str("purple"),
+            KakColor::Cyan => serializer.serialize_str("cyan"),
+            KakColor::White => serializer.serialize_str("white"),
+            KakColor::Default => serializer.serialize_str("default"),
+        }
+    }
+}
+
 /// An attribute in kakoune
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum KakAttribute {
     Underline,
@@ -118,7 +165,7 @@ pub enum KakAttribute {
 }
 
 /// A kakoune face
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct KakFace {
     pub fg: KakColor,
     pub bg: KakColor,
@@ -126,7 +173,7 @@ pub struct KakFace {
 }
 
 /// A kakoune atom
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct KakAtom {
     pub face: KakFace,
     pub contents: String,
@@ -136,14 +183,14 @@ pub struct KakAtom {
 pub type KakLine = Vec<KakAtom>;
 
 /// A coordinate in kakoune
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub struct KakCoord {
     pub line: u32,
     pub column: u32,
 }
 
 /// A incoming request. Recieve this from kakoune's stdout
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum IncomingRequest {
     Draw {
         lines: Vec<KakLine>,
@@ -186,7 +233,7 @@ pub enum IncomingRequest {
     },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 enum RawIncomingRequest {
@@ -213,6 +260,15 @@ impl<'de> Deserialize<'de> for IncomingRequest {
     }
 }
 
+impl Serialize for IncomingRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        JsonRpc::new(RawIncomingRequest::from(self.clone())).serialize(serializer)
+    }
+}
+
 impl From<RawIncomingRequest> for IncomingRequest {
     fn from(raw_request: RawIncomingRequest) -> Self {
         type Raw = RawIncomingRequest;
@@ -252,8 +308,47 @@ impl From<RawIncomingRequest> for IncomingRequest {
     }
 }
 
+impl From<IncomingRequest> for RawIncomingRequest {
+    fn from(request: IncomingRequest) -> Self {
+        type Raw = RawIncomingRequest;
+        type Processed = IncomingRequest;
+        match request {
+            Processed::Draw {
+                lines: a,
+                default_face: b,
+                padding_face: c,
+            } => Raw::Draw(a, b, c),
+            Processed::DrawStatus {
+                status_line: a,
+                mode_line: b,
+                default_face: c,
+            } => Raw::DrawStatus(a, b, c),
+            Processed::MenuShow {
+                items: a,
+                anchor: b,
+                selected_item_face: c,
+                menu_face: d,
+                style: e,
+            } => Raw::MenuShow(a, b, c, d, e),
+            Processed::MenuSelect { selected: a } => Raw::MenuSelect((a,)),
+            Processed::MenuHide => Raw::MenuHide([]),
+            Processed::InfoShow {
+                title: a,
+                content: b,
+                anchor: c,
+                face: d,
+                style: e,
+            } => Raw::InfoShow(a, b, c, d, e),
+            Processed::InfoHide => Raw::InfoHide([]),
+            Processed::SetCursor { mode: a, coord: b } => Raw::SetCursor(a, b),
+            Processed::SetUiOptions { options: a } => Raw::SetUiOptions((a,)),
+            Processed::Refresh { force: a } => Raw::Refresh((a,)),
+        }
+    }
+}
+
 /// A outgoing request. Input this to kakoune via stdin.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum OutgoingRequest {
     Keys(Vec<String>),
     Resize {
@@ -282,7 +377,7 @@ pub enum OutgoingRequest {
     },
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 enum RawOutgoingRequest {
@@ -331,6 +426,44 @@ impl From<OutgoingRequest> for RawOutgoingRequest {
     }
 }
 
+impl<'de> Deserialize<'de> for OutgoingRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(<JsonRpc<RawOutgoingRequest>>::deserialize(deserializer)?
+            .inner
+            .into())
+    }
+}
+
+impl From<RawOutgoingRequest> for OutgoingRequest {
+    fn from(raw_request: RawOutgoingRequest) -> Self {
+        type Raw = RawOutgoingRequest;
+        type Processed = OutgoingRequest;
+        match raw_request {
+            Raw::Keys(vec) => Processed::Keys(vec),
+            Raw::Resize(a, b) => Processed::Resize {
+                rows: a,
+                columns: b,
+            },
+            Raw::Scroll((a,)) => Processed::Scroll { amount: a },
+            Raw::MouseMove(a, b) => Processed::MouseMove { line: a, column: b },
+            Raw::MousePress(a, b, c) => Processed::MousePress {
+                button: a,
+                line: b,
+                column: c,
+            },
+            Raw::MouseRelease(a, b, c) => Processed::MouseRelease {
+                button: a,
+                line: b,
+                column: c,
+            },
+            Raw::MenuSelect((a,)) => Processed::MenuSelect { index: a },
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 struct JsonRpc<T> {
     jsonrpc: String,
@@ -346,3 +479,145 @@ impl<T> JsonRpc<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_color_round_trips_through_json() {
+        let json = r#""rgb:ff8800""#;
+        let color: KakColor = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            color,
+            KakColor::RGB {
+                r: 0xff,
+                g: 0x88,
+                b: 0x00
+            }
+        );
+        assert_eq!(serde_json::to_string(&color).unwrap(), json);
+    }
+
+    #[test]
+    fn rgba_color_round_trips_through_json() {
+        let json = r#""rgba:ff880080""#;
+        let color: KakColor = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            color,
+            KakColor::RGBA {
+                r: 0xff,
+                g: 0x88,
+                b: 0x00,
+                a: 0x80
+            }
+        );
+        assert_eq!(serde_json::to_string(&color).unwrap(), json);
+    }
+
+    #[test]
+    fn short_rgb_hex_is_rejected_instead_of_panicking() {
+        let result: Result<KakColor, _> = serde_json::from_str(r#""rgb:fff""#);
+        assert!(result.is_err());
+    }
+
+    fn sample_face() -> KakFace {
+        KakFace {
+            fg: KakColor::Red,
+            bg: KakColor::Default,
+            attributes: vec![KakAttribute::Bold],
+        }
+    }
+
+    fn sample_line(contents: &str) -> KakLine {
+        vec![KakAtom {
+            face: sample_face(),
+            contents: contents.to_string(),
+        }]
+    }
+
+    fn sample_coord() -> KakCoord {
+        KakCoord { line: 1, column: 2 }
+    }
+
+    fn incoming_request_cases() -> Vec<IncomingRequest> {
+        vec![
+            IncomingRequest::Draw {
+                lines: vec![sample_line("a")],
+                default_face: sample_face(),
+                padding_face: sample_face(),
+            },
+            IncomingRequest::DrawStatus {
+                status_line: sample_line("status"),
+                mode_line: sample_line("mode"),
+                default_face: sample_face(),
+            },
+            IncomingRequest::MenuShow {
+                items: vec![sample_line("item")],
+                anchor: sample_coord(),
+                selected_item_face: sample_face(),
+                menu_face: sample_face(),
+                style: "inline".to_string(),
+            },
+            IncomingRequest::MenuSelect { selected: 2 },
+            IncomingRequest::MenuHide,
+            IncomingRequest::InfoShow {
+                title: sample_line("title"),
+                content: vec![sample_line("content")],
+                anchor: sample_coord(),
+                face: sample_face(),
+                style: "info".to_string(),
+            },
+            IncomingRequest::InfoHide,
+            IncomingRequest::SetCursor {
+                mode: "insert".to_string(),
+                coord: sample_coord(),
+            },
+            IncomingRequest::SetUiOptions {
+                options: HashMap::from([("key".to_string(), "value".to_string())]),
+            },
+            IncomingRequest::Refresh { force: true },
+        ]
+    }
+
+    fn outgoing_request_cases() -> Vec<OutgoingRequest> {
+        vec![
+            OutgoingRequest::Keys(vec!["<esc>".to_string()]),
+            OutgoingRequest::Resize {
+                rows: 10,
+                columns: 20,
+            },
+            OutgoingRequest::Scroll { amount: 3 },
+            OutgoingRequest::MouseMove { line: 1, column: 2 },
+            OutgoingRequest::MousePress {
+                button: "left".to_string(),
+                line: 1,
+                column: 2,
+            },
+            OutgoingRequest::MouseRelease {
+                button: "left".to_string(),
+                line: 1,
+                column: 2,
+            },
+            OutgoingRequest::MenuSelect { index: 4 },
+        ]
+    }
+
+    #[test]
+    fn every_incoming_request_variant_round_trips_through_json() {
+        for request in incoming_request_cases() {
+            let json = serde_json::to_string(&request).unwrap();
+            let parsed: IncomingRequest = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, request, "round trip failed for {json}");
+        }
+    }
+
+    #[test]
+    fn every_outgoing_request_variant_round_trips_through_json() {
+        for request in outgoing_request_cases() {
+            let json = serde_json::to_string(&request).unwrap();
+            let parsed: OutgoingRequest = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, request, "round trip failed for {json}");
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Renders kakoune's drawing primitives ([`KakFace`], [`KakAtom`], [`KakLine`]) into ANSI
+//! escape sequences, so a terminal front-end can paint them directly.
+
+use crate::{KakAtom, KakAttribute, KakColor, KakFace, KakLine};
+
+const RESET: &str = "\x1b[0m";
+
+fn fg_code(color: &KakColor) -> String {
+    match color {
+        KakColor::RGB { r, g, b } | KakColor::RGBA { r, g, b, .. } => format!("38;2;{r};{g};{b}"),
+        KakColor::Black => "30".to_string(),
+        KakColor::Red => "31".to_string(),
+        KakColor::Green => "32".to_string(),
+        KakColor::Yellow => "33".to_string(),
+        KakColor::Blue => "34".to_string(),
+        KakColor::Purple => "35".to_string(),
+        KakColor::Cyan => "36".to_string(),
+        KakColor::White => "37".to_string(),
+        KakColor::Default => "39".to_string(),
+    }
+}
+
+fn bg_code(color: &KakColor) -> String {
+    match color {
+        KakColor::RGB { r, g, b } | KakColor::RGBA { r, g, b, .. } => format!("48;2;{r};{g};{b}"),
+        KakColor::Black => "40".to_string(),
+        KakColor::Red => "41".to_string(),
+        KakColor::Green => "42".to_string(),
+        KakColor::Yellow => "43".to_string(),
+        KakColor::Blue => "44".to_string(),
+        KakColor::Purple => "45".to_string(),
+        KakColor::Cyan => "46".to_string(),
+        KakColor::White => "47".to_string(),
+        KakColor::Default => "49".to_string(),
+    }
+}
+
+fn attribute_code(attribute: &KakAttribute) -> Option<&'static str> {
+    match attribute {
+        KakAttribute::Bold => Some("1"),
+        KakAttribute::Dim => Some("2"),
+        KakAttribute::Italic => Some("3"),
+        KakAttribute::Underline => Some("4"),
+        KakAttribute::Blink => Some("5"),
+        KakAttribute::Reverse => Some("7"),
+        KakAttribute::FinalFg | KakAttribute::FinalBg | KakAttribute::FinalAttr => None,
+    }
+}
+
+/// Resolves a face's colors and attributes against `default`, substituting
+/// [`KakColor::Default`] and merging in `default`'s attributes unless the face marks
+/// itself final with [`KakAttribute::FinalFg`]/[`KakAttribute::FinalBg`]/[`KakAttribute::FinalAttr`].
+pub(crate) fn resolve_face(face: &KakFace, default: &KakFace) -> KakFace {
+    let fg = if !face.attributes.contains(&KakAttribute::FinalFg) && face.fg == KakColor::Default {
+        default.fg
+    } else {
+        face.fg
+    };
+    let bg = if !face.attributes.contains(&KakAttribute::FinalBg) && face.bg == KakColor::Default {
+        default.bg
+    } else {
+        face.bg
+    };
+
+    let mut attributes = if face.attributes.contains(&KakAttribute::FinalAttr) {
+        vec![]
+    } else {
+        default.attributes.clone()
+    };
+    attributes.extend(face.attributes.iter().cloned());
+
+    KakFace { fg, bg, attributes }
+}
+
+/// Returns the ANSI SGR parameter codes (e.g. `"38;2;255;0;0"`, `"1"`) for a face that has
+/// already been resolved with [`resolve_face`].
+pub(crate) fn face_sgr_codes(face: &KakFace) -> Vec<String> {
+    let mut codes = vec![fg_code(&face.fg), bg_code(&face.bg)];
+    codes.extend(
+        face.attributes
+            .iter()
+            .filter_map(attribute_code)
+            .map(str::to_string),
+    );
+    codes
+}
+
+/// Renders a single [`KakAtom`] as an ANSI-escaped string, resolving any color/attribute
+/// inheritance from `default`.
+fn render_atom(atom: &KakAtom, default: &KakFace) -> String {
+    let codes = face_sgr_codes(&resolve_face(&atom.face, default));
+    format!("\x1b[{}m{}{}", codes.join(";"), atom.contents, RESET)
+}
+
+/// Renders a [`KakLine`] as an ANSI-escaped string, using `default` to resolve any
+/// [`KakColor::Default`] colors and attributes that aren't marked final.
+pub fn render_line(line: &KakLine, default: &KakFace) -> String {
+    line.iter().map(|atom| render_atom(atom, default)).collect()
+}
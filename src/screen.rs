@@ -0,0 +1,465 @@
+//! A grid-of-cells model of what kakoune has drawn onto the terminal, along with minimal-diff
+//! repainting so a client only has to redraw what changed between two frames.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::render::{face_sgr_codes, resolve_face};
+use crate::{IncomingRequest, KakColor, KakCoord, KakFace, KakLine};
+
+/// A single rendered terminal cell: a character plus its fully-resolved (non-inheriting) face.
+///
+/// Double-width characters (e.g. CJK, emoji) occupy two consecutive cells: the first holds the
+/// character itself, and the second is a blank [`Cell::is_wide_continuation`] cell, mirroring how
+/// a vt100-style grid reserves the following column so indexing stays aligned with what was
+/// actually drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub face: KakFace,
+    pub is_wide_continuation: bool,
+}
+
+impl Cell {
+    fn blank(face: KakFace) -> Self {
+        Self {
+            ch: ' ',
+            face,
+            is_wide_continuation: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MenuState {
+    items: Vec<KakLine>,
+    anchor: KakCoord,
+    selected: Option<u32>,
+    selected_item_face: KakFace,
+    menu_face: KakFace,
+}
+
+#[derive(Debug, Clone)]
+struct InfoState {
+    title: KakLine,
+    content: Vec<KakLine>,
+    anchor: KakCoord,
+    face: KakFace,
+}
+
+/// A grid of styled [`Cell`]s mirroring what kakoune has drawn onto the terminal, sized by the
+/// last [`crate::OutgoingRequest::Resize`] sent to kakoune.
+///
+/// Feed it every [`IncomingRequest`] via [`Screen::apply`], then use [`Screen::diff_to_ansi`] to
+/// repaint only the cells that changed since a previous frame.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    rows: u32,
+    columns: u32,
+    cells: Vec<Cell>,
+    cursor: KakCoord,
+    menu: Option<MenuState>,
+    info: Option<InfoState>,
+}
+
+impl Screen {
+    /// Creates a blank screen of the given size, as if it had just been resized.
+    pub fn new(rows: u32, columns: u32) -> Self {
+        let blank_face = KakFace {
+            fg: KakColor::Default,
+            bg: KakColor::Default,
+            attributes: vec![],
+        };
+        Self {
+            rows,
+            columns,
+            cells: vec![Cell::blank(blank_face); (rows * columns) as usize],
+            cursor: KakCoord { line: 0, column: 0 },
+            menu: None,
+            info: None,
+        }
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn cursor(&self) -> KakCoord {
+        self.cursor
+    }
+
+    /// Returns the cell at `(line, column)` in the base body/status grid, or `None` if it's out
+    /// of bounds. This does not include any menu/info overlay; use [`Screen::diff_to_ansi`] to
+    /// render those composited on top.
+    pub fn cell(&self, line: u32, column: u32) -> Option<&Cell> {
+        if line >= self.rows || column >= self.columns {
+            return None;
+        }
+        self.cells.get(self.index(line, column))
+    }
+
+    fn index(&self, line: u32, column: u32) -> usize {
+        (line * self.columns + column) as usize
+    }
+
+    fn resize(&mut self, rows: u32, columns: u32) {
+        *self = Screen::new(rows, columns);
+    }
+
+    /// Writes `line` starting at `(row, start_column)` into `cells`, resolving colors/attributes
+    /// against `default_face` and reserving a second, [`Cell::is_wide_continuation`] cell for
+    /// any double-width character.
+    fn write_line_into(
+        cells: &mut [Cell],
+        columns: u32,
+        row: u32,
+        start_column: u32,
+        line: &KakLine,
+        default_face: &KakFace,
+    ) {
+        let mut column = start_column;
+        for atom in line {
+            let face = resolve_face(&atom.face, default_face);
+            for ch in atom.contents.chars() {
+                if column >= columns {
+                    return;
+                }
+                let width = ch.width().unwrap_or(1).max(1);
+                let index = (row * columns + column) as usize;
+                cells[index] = Cell {
+                    ch,
+                    face: face.clone(),
+                    is_wide_continuation: false,
+                };
+                column += 1;
+                if width == 2 && column < columns {
+                    let index = (row * columns + column) as usize;
+                    cells[index] = Cell {
+                        ch: ' ',
+                        face: face.clone(),
+                        is_wide_continuation: true,
+                    };
+                    column += 1;
+                }
+            }
+        }
+    }
+
+    fn write_line(&mut self, row: u32, line: &KakLine, default_face: &KakFace) {
+        if row >= self.rows {
+            return;
+        }
+        Self::write_line_into(&mut self.cells, self.columns, row, 0, line, default_face);
+        let written: u32 = line
+            .iter()
+            .flat_map(|atom| atom.contents.chars())
+            .map(|ch| ch.width().unwrap_or(1).max(1) as u32)
+            .sum();
+        let padding = resolve_face(default_face, default_face);
+        let mut column = written.min(self.columns);
+        while column < self.columns {
+            let index = self.index(row, column);
+            self.cells[index] = Cell::blank(padding.clone());
+            column += 1;
+        }
+    }
+
+    /// Folds an [`IncomingRequest`] into this screen. `Draw`/`DrawStatus`/`SetCursor` update the
+    /// body/status grid directly. `MenuShow`/`MenuSelect`/`MenuHide` and `InfoShow`/`InfoHide`
+    /// update overlay state tracked separately from the grid, which [`Screen::diff_to_ansi`]
+    /// composites on top when rendering, so they never overwrite the cells underneath them.
+    pub fn apply(&mut self, request: &IncomingRequest) {
+        match request {
+            IncomingRequest::Draw {
+                lines,
+                default_face,
+                padding_face,
+            } => {
+                for (row, line) in lines.iter().enumerate() {
+                    self.write_line(row as u32, line, default_face);
+                }
+                for row in lines.len() as u32..self.rows.saturating_sub(1) {
+                    self.write_line(row, &vec![], padding_face);
+                }
+            }
+            IncomingRequest::DrawStatus {
+                status_line,
+                mode_line,
+                default_face,
+            } => {
+                if self.rows == 0 {
+                    return;
+                }
+                let row = self.rows - 1;
+                self.write_line(row, status_line, default_face);
+                let mode_width: u32 = mode_line
+                    .iter()
+                    .flat_map(|atom| atom.contents.chars())
+                    .map(|ch| ch.width().unwrap_or(1).max(1) as u32)
+                    .sum();
+                if mode_width <= self.columns {
+                    let start_column = self.columns - mode_width;
+                    Self::write_line_into(
+                        &mut self.cells,
+                        self.columns,
+                        row,
+                        start_column,
+                        mode_line,
+                        default_face,
+                    );
+                }
+            }
+            IncomingRequest::SetCursor { coord, .. } => {
+                self.cursor = *coord;
+            }
+            IncomingRequest::MenuShow {
+                items,
+                anchor,
+                selected_item_face,
+                menu_face,
+                style: _,
+            } => {
+                self.menu = Some(MenuState {
+                    items: items.clone(),
+                    anchor: *anchor,
+                    selected: None,
+                    selected_item_face: selected_item_face.clone(),
+                    menu_face: menu_face.clone(),
+                });
+            }
+            IncomingRequest::MenuSelect { selected } => {
+                if let Some(menu) = &mut self.menu {
+                    menu.selected = Some(*selected);
+                }
+            }
+            IncomingRequest::MenuHide => {
+                self.menu = None;
+            }
+            IncomingRequest::InfoShow {
+                title,
+                content,
+                anchor,
+                face,
+                style: _,
+            } => {
+                self.info = Some(InfoState {
+                    title: title.clone(),
+                    content: content.clone(),
+                    anchor: *anchor,
+                    face: face.clone(),
+                });
+            }
+            IncomingRequest::InfoHide => {
+                self.info = None;
+            }
+            IncomingRequest::SetUiOptions { .. } | IncomingRequest::Refresh { .. } => {}
+        }
+    }
+
+    /// Resizes the screen, as should be done whenever an `resize` [`crate::OutgoingRequest`] is
+    /// sent to kakoune. All cells are reset to blank, since kakoune will redraw from scratch.
+    pub fn apply_resize(&mut self, rows: u32, columns: u32) {
+        self.resize(rows, columns);
+    }
+
+    /// Returns the body/status grid with the current menu (if any) and info box (if any)
+    /// composited on top, in that order, as kakoune draws them as overlays rather than by
+    /// mutating the underlying text.
+    fn effective_cells(&self) -> Vec<Cell> {
+        let mut cells = self.cells.clone();
+        if let Some(menu) = &self.menu {
+            for (i, item) in menu.items.iter().enumerate() {
+                let face = if menu.selected == Some(i as u32) {
+                    &menu.selected_item_face
+                } else {
+                    &menu.menu_face
+                };
+                let row = menu.anchor.line + i as u32;
+                if row < self.rows {
+                    Self::write_line_into(
+                        &mut cells,
+                        self.columns,
+                        row,
+                        menu.anchor.column,
+                        item,
+                        face,
+                    );
+                }
+            }
+        }
+        if let Some(info) = &self.info {
+            if info.anchor.line < self.rows {
+                Self::write_line_into(
+                    &mut cells,
+                    self.columns,
+                    info.anchor.line,
+                    info.anchor.column,
+                    &info.title,
+                    &info.face,
+                );
+            }
+            for (i, line) in info.content.iter().enumerate() {
+                let row = info.anchor.line + 1 + i as u32;
+                if row < self.rows {
+                    Self::write_line_into(
+                        &mut cells,
+                        self.columns,
+                        row,
+                        info.anchor.column,
+                        line,
+                        &info.face,
+                    );
+                }
+            }
+        }
+        cells
+    }
+
+    /// Emits only the cursor-move + SGR + text runs for cells that changed since `prev`
+    /// (including any menu/info overlay), so a client can repaint a frame incrementally instead
+    /// of clearing the whole screen.
+    pub fn diff_to_ansi(&self, prev: &Screen) -> String {
+        let current = self.effective_cells();
+        let previous = prev.effective_cells();
+        let mut out = String::new();
+        for row in 0..self.rows {
+            let mut column = 0;
+            while column < self.columns {
+                let index = self.index(row, column);
+                let changed = prev.rows != self.rows
+                    || prev.columns != self.columns
+                    || previous.get(index) != current.get(index);
+                if !changed {
+                    column += 1;
+                    continue;
+                }
+                let face = &current[index].face;
+                let run_start = column;
+                let mut run = String::new();
+                while column < self.columns {
+                    let index = self.index(row, column);
+                    let changed = prev.rows != self.rows
+                        || prev.columns != self.columns
+                        || previous.get(index) != current.get(index);
+                    if !changed || &current[index].face != face {
+                        break;
+                    }
+                    if !current[index].is_wide_continuation {
+                        run.push(current[index].ch);
+                    }
+                    column += 1;
+                }
+                out.push_str(&format!("\x1b[{};{}H", row + 1, run_start + 1));
+                out.push_str(&format!("\x1b[{}m", face_sgr_codes(face).join(";")));
+                out.push_str(&run);
+                out.push_str("\x1b[0m");
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KakAtom;
+
+    fn face(fg: KakColor, bg: KakColor) -> KakFace {
+        KakFace {
+            fg,
+            bg,
+            attributes: vec![],
+        }
+    }
+
+    fn atom(contents: &str, face: KakFace) -> KakAtom {
+        KakAtom {
+            face,
+            contents: contents.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_is_empty_for_two_identical_frames() {
+        let default_face = face(KakColor::Default, KakColor::Default);
+        let draw = IncomingRequest::Draw {
+            lines: vec![vec![atom("hi", face(KakColor::Red, KakColor::Default))]],
+            default_face: default_face.clone(),
+            padding_face: default_face,
+        };
+        let mut prev = Screen::new(1, 5);
+        let mut current = Screen::new(1, 5);
+        prev.apply(&draw);
+        current.apply(&draw);
+        assert_eq!(current.diff_to_ansi(&prev), "");
+    }
+
+    #[test]
+    fn diff_repaints_only_the_changed_run() {
+        let default_face = face(KakColor::Default, KakColor::Default);
+        let mut prev = Screen::new(1, 5);
+        prev.apply(&IncomingRequest::Draw {
+            lines: vec![vec![atom("aaaaa", default_face.clone())]],
+            default_face: default_face.clone(),
+            padding_face: default_face.clone(),
+        });
+        let mut current = prev.clone();
+        current.apply(&IncomingRequest::Draw {
+            lines: vec![vec![atom("aabaa", default_face.clone())]],
+            default_face: default_face.clone(),
+            padding_face: default_face,
+        });
+        let diff = current.diff_to_ansi(&prev);
+        assert!(diff.contains('b'));
+        assert_eq!(diff.matches('a').count(), 0);
+    }
+
+    #[test]
+    fn wide_characters_reserve_a_continuation_cell() {
+        let default_face = face(KakColor::Default, KakColor::Default);
+        let mut screen = Screen::new(1, 4);
+        screen.apply(&IncomingRequest::Draw {
+            lines: vec![vec![atom("\u{6f22}a", default_face.clone())]],
+            default_face: default_face.clone(),
+            padding_face: default_face,
+        });
+        assert_eq!(screen.cell(0, 0).unwrap().ch, '\u{6f22}');
+        assert!(!screen.cell(0, 0).unwrap().is_wide_continuation);
+        assert!(screen.cell(0, 1).unwrap().is_wide_continuation);
+        assert_eq!(screen.cell(0, 2).unwrap().ch, 'a');
+    }
+
+    #[test]
+    fn menu_show_composites_above_the_grid_without_overwriting_it() {
+        let default_face = face(KakColor::Default, KakColor::Default);
+        let mut screen = Screen::new(3, 10);
+        screen.apply(&IncomingRequest::Draw {
+            lines: vec![
+                vec![atom("aaaaaaaaaa", default_face.clone())],
+                vec![atom("bbbbbbbbbb", default_face.clone())],
+            ],
+            default_face: default_face.clone(),
+            padding_face: default_face.clone(),
+        });
+        screen.apply(&IncomingRequest::MenuShow {
+            items: vec![vec![atom("menu", default_face.clone())]],
+            anchor: KakCoord { line: 1, column: 2 },
+            selected_item_face: default_face.clone(),
+            menu_face: default_face.clone(),
+            style: "inline".to_string(),
+        });
+
+        assert_eq!(screen.cell(1, 0).unwrap().ch, 'b');
+
+        let blank = Screen::new(3, 10);
+        let diff = screen.diff_to_ansi(&blank);
+        assert!(diff.contains("menu"));
+
+        screen.apply(&IncomingRequest::MenuHide);
+        let diff_after_hide = screen.diff_to_ansi(&blank);
+        assert!(!diff_after_hide.contains("menu"));
+    }
+}